@@ -2,16 +2,20 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::convert::TryInto;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::json_types::{ValidAccountId, U128, U64};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, AccountId, Balance, Promise};
+use near_sdk::{env, AccountId, Balance, Gas, Promise};
 
 pub(crate) const FROM_NANO: u64 = 1_000_000_000;
 
+/// Upper bound on the number of actions a single proposal may batch, to
+/// keep `Proposal::execute` within the gas limit of one receipt chain.
+pub(crate) const MAX_ACTIONS: usize = 10;
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Voter {
@@ -20,11 +24,51 @@ pub struct Voter {
     pub power: u16,
 }
 
+/// A single voter's cast ballot, recorded so it can be changed or revoked
+/// while voting is still active.
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VoteRecord {
+    pub vote_yes: bool,
+    /// Base `Voter.power` scaled by the conviction multiplier for the
+    /// chosen `lock_period`; this is what was added to the tally.
+    pub effective_power: u32,
+    /// Block timestamp (seconds) after which the voter's lock from this
+    /// ballot's `lock_period` is released.
+    pub unlock_at: u64,
+}
+
+/// Conviction-voting multiplier ladder: `lock_period` (capped at the last
+/// rung) maps to a `(numerator, denominator)` scaling applied to a voter's
+/// base power. Longer locks grant proportionally greater influence.
+const LOCK_MULTIPLIERS: [(u32, u32); 5] = [(1, 10), (1, 1), (2, 1), (4, 1), (8, 1)];
+
+/// Highest `lock_period` rung on the conviction ladder.
+pub(crate) const MAX_LOCK_PERIOD: u8 = (LOCK_MULTIPLIERS.len() - 1) as u8;
+
+/// Scales `base_power` by the conviction multiplier for `lock_period`,
+/// capping `lock_period` at the top rung of the ladder.
+fn conviction_power(base_power: u32, lock_period: u8) -> u32 {
+    let (num, den) = LOCK_MULTIPLIERS[lock_period.min(MAX_LOCK_PERIOD) as usize];
+    base_power.saturating_mul(num) / den
+}
+
 /// Internal Action representation
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum ActionInt {
-    Transfer { dest: AccountId, amount: Balance },
-    Delete { dest: AccountId },
+    Transfer {
+        dest: AccountId,
+        amount: Balance,
+    },
+    Delete {
+        dest: AccountId,
+    },
+    FunctionCall {
+        dest: AccountId,
+        method: String,
+        args: Vec<u8>,
+        deposit: Balance,
+        gas: Gas,
+    },
 }
 
 /// Action is a JSON compatible type for encodidng actions
@@ -32,8 +76,20 @@ pub enum ActionInt {
 #[cfg_attr(feature = "test", derive(Clone, Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
 pub enum Action {
-    Transfer { dest: ValidAccountId, amount: U128 },
-    Delete { dest: ValidAccountId },
+    Transfer {
+        dest: ValidAccountId,
+        amount: U128,
+    },
+    Delete {
+        dest: ValidAccountId,
+    },
+    FunctionCall {
+        dest: ValidAccountId,
+        method: String,
+        args: Base64VecU8,
+        deposit: U128,
+        gas: U64,
+    },
 }
 
 impl Action {
@@ -47,6 +103,77 @@ impl Action {
             Action::Delete { dest } => ActionInt::Delete {
                 dest: dest.clone().into(),
             },
+            Action::FunctionCall {
+                dest,
+                method,
+                args,
+                deposit,
+                gas,
+            } => ActionInt::FunctionCall {
+                dest: dest.clone().into(),
+                method: method.clone(),
+                args: args.clone().into(),
+                deposit: deposit.clone().into(),
+                gas: u64::from(*gas),
+            },
+        }
+    }
+
+    /// Validates the action at proposal-creation time, before it is stored.
+    fn validate(&self) {
+        if let Action::FunctionCall { method, .. } = self {
+            assert!(!method.is_empty(), "function call method must not be empty");
+        }
+    }
+}
+
+impl ActionInt {
+    /// Creates `Action` from this object without consuming it, for event
+    /// logging where the proposal's actions must stay in place.
+    fn to_action(&self) -> Action {
+        match self {
+            ActionInt::Transfer { dest, amount } => Action::Transfer {
+                dest: dest.clone().try_into().unwrap(),
+                amount: (*amount).into(),
+            },
+            ActionInt::Delete { dest } => Action::Delete {
+                dest: dest.clone().try_into().unwrap(),
+            },
+            ActionInt::FunctionCall {
+                dest,
+                method,
+                args,
+                deposit,
+                gas,
+            } => Action::FunctionCall {
+                dest: dest.clone().try_into().unwrap(),
+                method: method.clone(),
+                args: args.clone().into(),
+                deposit: (*deposit).into(),
+                gas: (*gas).into(),
+            },
+        }
+    }
+
+    /// Builds the `Promise` that performs this action.
+    fn to_promise(&self) -> Promise {
+        match self {
+            ActionInt::Transfer { dest, amount } => Promise::new(dest.clone()).transfer(*amount),
+            ActionInt::Delete { dest } => {
+                Promise::new(env::current_account_id()).delete_account(dest.clone())
+            }
+            ActionInt::FunctionCall {
+                dest,
+                method,
+                args,
+                deposit,
+                gas,
+            } => Promise::new(dest.clone()).function_call(
+                method.clone().into_bytes(),
+                args.clone(),
+                *deposit,
+                *gas,
+            ),
         }
     }
 }
@@ -61,16 +188,108 @@ impl Into<Action> for ActionInt {
             ActionInt::Delete { dest } => Action::Delete {
                 dest: dest.try_into().unwrap(),
             },
+            ActionInt::FunctionCall {
+                dest,
+                method,
+                args,
+                deposit,
+                gas,
+            } => Action::FunctionCall {
+                dest: dest.try_into().unwrap(),
+                method,
+                args: args.into(),
+                deposit: deposit.into(),
+                gas: gas.into(),
+            },
         }
     }
 }
 
+/// NEP-297 event data for a newly created proposal.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalCreatedData<'a> {
+    pub proposer: &'a AccountId,
+    pub description: &'a str,
+    pub actions: Vec<Action>,
+    pub voting_start: U64,
+    pub voting_end: U64,
+}
+
+/// NEP-297 event data for a single cast (or changed) vote.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteCastData<'a> {
+    pub voter: &'a AccountId,
+    pub power: u16,
+    pub lock_period: u8,
+    pub effective_power: u32,
+    pub vote_yes: bool,
+    pub votes_for: u32,
+    pub votes_against: u32,
+}
+
+/// NEP-297 event data for a withdrawn vote.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRevokedData<'a> {
+    pub voter: &'a AccountId,
+    pub votes_for: u32,
+    pub votes_against: u32,
+}
+
+/// NEP-297 event data for an executed proposal.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalExecutedData {
+    pub votes_for: u32,
+    pub votes_against: u32,
+    pub actions: Vec<Action>,
+}
+
+/// NEP-297 compliant events emitted across the proposal lifecycle, so
+/// off-chain indexers and notification bots can track governance without
+/// scraping full contract state.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum Event<'a> {
+    ProposalCreated(Vec<ProposalCreatedData<'a>>),
+    VoteCast(Vec<VoteCastData<'a>>),
+    VoteRevoked(Vec<VoteRevokedData<'a>>),
+    ProposalExecuted(Vec<ProposalExecutedData>),
+}
+
+impl<'a> Event<'a> {
+    /// Logs this event as `EVENT_JSON:{...}`, per the NEP-297 standard.
+    pub(crate) fn emit(&self) {
+        #[derive(Serialize)]
+        #[serde(crate = "near_sdk::serde")]
+        struct EventJson<'b, 'c> {
+            standard: &'static str,
+            version: &'static str,
+            #[serde(flatten)]
+            event: &'c Event<'b>,
+        }
+        let envelope = EventJson {
+            standard: "vostok_dao",
+            version: "1.0.0",
+            event: self,
+        };
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&envelope).unwrap()
+        ));
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct Proposal {
     pub proposer: AccountId,
     pub description: String,
-    pub action: ActionInt,
-    pub voters: HashSet<AccountId>,
+    pub actions: Vec<ActionInt>,
+    pub voters: HashMap<AccountId, VoteRecord>,
     /// Block timestamp in seconds when voting starts.
     pub voting_start: u64,
     /// Block timestamp in seconds when voting ends.
@@ -79,33 +298,207 @@ pub struct Proposal {
     pub votes_against: u32,
     pub execute_before: u64,
     pub executed: bool,
+    /// Quorum required to execute, in basis points of `total_power`.
+    pub quorum_bps: u16,
+    /// Snapshot of total registered voting power, taken at proposal creation.
+    pub total_power: u32,
+    /// When set, voting happens in two phases: a commit phase ending at
+    /// `commit_end`, then a reveal phase lasting until `voting_end`. When
+    /// unset, `vote`/`revoke` apply directly as in the single-phase mode.
+    pub commit_reveal: bool,
+    /// Block timestamp in seconds when the commit phase ends and the
+    /// reveal phase begins. Unused outside commit-reveal mode.
+    pub commit_end: u64,
+    /// Per-account commitments awaiting reveal: `sha256(vote_yes || salt ||
+    /// account_id)`. Entries are removed once revealed; ballots that are
+    /// never revealed stay out of `votes_for`/`votes_against` and so are
+    /// excluded from the quorum base.
+    pub commitments: HashMap<AccountId, Vec<u8>>,
 }
 
 impl Proposal {
-    pub fn vote(&mut self, voter: &Voter, vote_yes: bool) {
+    /// Casts (or changes) `voter`'s ballot. `lock_period` (capped at
+    /// `MAX_LOCK_PERIOD`) selects the conviction multiplier applied to the
+    /// voter's base power, and locks the ballot until `voting_end +
+    /// lock_period * base_lock_seconds` (see `unlock_at`); while any voter
+    /// is locked, `execute` refuses to run this proposal's `Delete` actions,
+    /// whatever their `dest`.
+    pub fn vote(&mut self, voter: &Voter, vote_yes: bool, lock_period: u8, base_lock_seconds: u64) {
+        assert!(
+            !self.commit_reveal,
+            "this proposal uses commit-reveal voting; call commit/reveal instead"
+        );
         let t: u64 = env::block_timestamp() / FROM_NANO;
         assert!(
             self.voting_start <= t && self.voting_end >= t,
             "voting is not active"
         );
+        self.record_vote(voter, vote_yes, lock_period, base_lock_seconds);
+    }
+
+    /// Withdraws a previously cast vote, removing its power from the tally
+    /// it was applied to. No-op error if the caller never voted.
+    pub fn revoke(&mut self, voter: &Voter) {
         assert!(
-            self.voters.insert(voter.account.clone()),
-            "you already voted"
+            !self.commit_reveal,
+            "this proposal uses commit-reveal voting; ballots can't be revoked"
         );
-        let p: u32 = voter.power.into();
+        let t: u64 = env::block_timestamp() / FROM_NANO;
+        assert!(
+            self.voting_start <= t && self.voting_end >= t,
+            "voting is not active"
+        );
+        let record = self
+            .voters
+            .remove(&voter.account)
+            .expect("you haven't voted");
+        self.unapply(&record);
+        Event::VoteRevoked(vec![VoteRevokedData {
+            voter: &voter.account,
+            votes_for: self.votes_for,
+            votes_against: self.votes_against,
+        }])
+        .emit();
+    }
+
+    /// Submits `commitment = sha256(vote_yes || salt || account_id)` for
+    /// `account`, without revealing the vote. Only valid in commit-reveal
+    /// mode during `[voting_start, commit_end]`; may be called again before
+    /// `commit_end` to replace a previous commitment.
+    pub fn commit(&mut self, account: &AccountId, commitment: Vec<u8>) {
+        assert!(
+            self.commit_reveal,
+            "this proposal uses direct voting; call vote instead"
+        );
+        let t: u64 = env::block_timestamp() / FROM_NANO;
+        assert!(
+            self.voting_start <= t && t <= self.commit_end,
+            "commit phase is not active"
+        );
+        self.commitments.insert(account.clone(), commitment);
+    }
+
+    /// Reveals a previously committed ballot. Recomputes `sha256(vote_yes ||
+    /// salt || voter.account)` and asserts it matches the stored
+    /// commitment, then applies `voter.power` exactly as `vote` would.
+    /// Only valid in commit-reveal mode during `(commit_end, voting_end]`.
+    pub fn reveal(
+        &mut self,
+        voter: &Voter,
+        vote_yes: bool,
+        salt: &[u8],
+        lock_period: u8,
+        base_lock_seconds: u64,
+    ) {
+        assert!(
+            self.commit_reveal,
+            "this proposal uses direct voting; call vote instead"
+        );
+        let t: u64 = env::block_timestamp() / FROM_NANO;
+        assert!(
+            self.commit_end < t && t <= self.voting_end,
+            "reveal phase is not active"
+        );
+        let commitment = self
+            .commitments
+            .remove(&voter.account)
+            .expect("no commitment to reveal");
+        let mut preimage = vec![vote_yes as u8];
+        preimage.extend_from_slice(salt);
+        preimage.extend_from_slice(voter.account.as_bytes());
+        assert_eq!(
+            env::sha256(&preimage),
+            commitment,
+            "revealed vote doesn't match the committed hash"
+        );
+        self.record_vote(voter, vote_yes, lock_period, base_lock_seconds);
+    }
+
+    /// Returns the block timestamp (seconds) after which `account`'s
+    /// conviction lock from voting on this proposal is released, if they
+    /// voted at all.
+    pub fn unlock_at(&self, account: &AccountId) -> Option<u64> {
+        self.voters.get(account).map(|r| r.unlock_at)
+    }
+
+    /// Asserts that no voter recorded against this proposal is still
+    /// within their conviction lock, so it's safe to `Delete`. A `Delete`
+    /// action always targets `env::current_account_id()` (see
+    /// `ActionInt::to_promise`) -- the contract account itself, not the
+    /// action's `dest` beneficiary -- so every voter on this proposal has
+    /// a stake in the lock, not just whoever `dest` happens to name.
+    fn assert_no_locked_voters(&self) {
+        let t: u64 = env::block_timestamp() / FROM_NANO;
+        for (account, record) in &self.voters {
+            assert!(
+                t >= record.unlock_at,
+                "{} is locked by a conviction vote on this proposal until {} [seconds]",
+                account,
+                record.unlock_at
+            );
+        }
+    }
+
+    /// Applies a ballot to the tallies, superseding any previous ballot by
+    /// the same voter, and emits the `vote_cast` event. Shared by the
+    /// direct-vote and reveal paths.
+    fn record_vote(
+        &mut self,
+        voter: &Voter,
+        vote_yes: bool,
+        lock_period: u8,
+        base_lock_seconds: u64,
+    ) {
+        if let Some(prev) = self.voters.get(&voter.account) {
+            self.unapply(prev);
+        }
+        let effective_power = conviction_power(voter.power.into(), lock_period);
         if vote_yes {
-            self.votes_for += p;
+            self.votes_for = self.votes_for.saturating_add(effective_power);
+        } else {
+            self.votes_against = self.votes_against.saturating_add(effective_power);
+        }
+        let unlock_at =
+            self.voting_end + u64::from(lock_period.min(MAX_LOCK_PERIOD)) * base_lock_seconds;
+        self.voters.insert(
+            voter.account.clone(),
+            VoteRecord {
+                vote_yes,
+                effective_power,
+                unlock_at,
+            },
+        );
+        Event::VoteCast(vec![VoteCastData {
+            voter: &voter.account,
+            power: voter.power,
+            lock_period,
+            effective_power,
+            vote_yes,
+            votes_for: self.votes_for,
+            votes_against: self.votes_against,
+        }])
+        .emit();
+    }
+
+    /// Subtracts a previously recorded vote's power from the tally it was
+    /// cast into.
+    fn unapply(&mut self, record: &VoteRecord) {
+        if record.vote_yes {
+            self.votes_for = self.votes_for.saturating_sub(record.effective_power);
         } else {
-            self.votes_against += p;
+            self.votes_against = self.votes_against.saturating_sub(record.effective_power);
         }
     }
 
-    pub fn execute(&mut self, min_support: u32) -> Promise {
+    /// `min_action_delay` is the timelock, in seconds, that must elapse
+    /// after `voting_end` before a passed proposal can execute.
+    pub fn execute(&mut self, min_support: u32, min_action_delay: u32) -> Promise {
         let t: u64 = env::block_timestamp() / FROM_NANO;
+        let earliest = self.voting_end + u64::from(min_action_delay);
         assert!(
-            self.voting_end < t && t <= self.execute_before,
+            earliest < t && t <= self.execute_before,
             "proposal can be executed only between {} and {} timestamp [seconds]",
-            self.voting_end + 1,
+            earliest + 1,
             self.execute_before
         );
         assert!(
@@ -120,14 +513,38 @@ impl Proposal {
             self.votes_for,
             self.votes_against
         );
+        let total_votes: u64 = u64::from(self.votes_for) + u64::from(self.votes_against);
+        assert!(
+            total_votes * 10000 >= u64::from(self.total_power) * u64::from(self.quorum_bps),
+            "proposal didn't reach quorum ({} bps of {} required, got {} votes)",
+            self.quorum_bps,
+            self.total_power,
+            total_votes
+        );
         assert!(!self.executed, "proposal already executed");
+        if self
+            .actions
+            .iter()
+            .any(|a| matches!(a, ActionInt::Delete { .. }))
+        {
+            self.assert_no_locked_voters();
+        }
         self.executed = true;
-        match &self.action {
-            ActionInt::Transfer { dest, amount } => Promise::new(dest.clone()).transfer(*amount),
-            ActionInt::Delete { dest } => {
-                Promise::new(env::current_account_id()).delete_account(dest.clone())
-            }
+        Event::ProposalExecuted(vec![ProposalExecutedData {
+            votes_for: self.votes_for,
+            votes_against: self.votes_against,
+            actions: self.actions.iter().map(ActionInt::to_action).collect(),
+        }])
+        .emit();
+        let mut actions = self.actions.iter();
+        let mut promise = actions
+            .next()
+            .expect("proposal has no actions")
+            .to_promise();
+        for action in actions {
+            promise = promise.then(action.to_promise());
         }
+        promise
     }
 }
 
@@ -136,7 +553,9 @@ impl Proposal {
 #[cfg_attr(feature = "test", derive(Clone))]
 #[serde(crate = "near_sdk::serde")]
 pub struct NewProposal {
-    pub action: Action,
+    /// Ordered batch of actions the proposal executes if it passes. Must
+    /// contain at least one and at most `MAX_ACTIONS` actions.
+    pub actions: Vec<Action>,
     pub description: String,
     /// Unix timestamp (in seconds) when the voting starts.
     /// Must be bigger than current block timestamp.
@@ -147,11 +566,40 @@ pub struct NewProposal {
     /// Last block timestamp (in seconds) when the proposal can be executed.
     /// Must be bigger than `voting_start + voting_duration`.
     pub execute_before: U64,
+    /// When `true`, voting happens in two phases (see `Proposal::commit`/
+    /// `Proposal::reveal`) instead of direct `vote`/`revoke` calls.
+    pub commit_reveal: bool,
+    /// Unix timestamp (in seconds) ending the commit phase. Required (and
+    /// must fall strictly between `voting_start` and `voting_end`) when
+    /// `commit_reveal` is set; ignored otherwise.
+    pub commit_end: U64,
 }
 
 impl NewProposal {
-    /// `min_duration` and `max_duration` is expressed in seconds.
-    pub fn into_proposal(&self, min_duration: u32, max_duration: u32) -> Proposal {
+    /// `min_duration` and `max_duration` is expressed in seconds. `quorum_bps`
+    /// (0..=10000) and `total_power` (the snapshot of total registered voting
+    /// power) are stored on the proposal and used by `Proposal::execute` to
+    /// enforce quorum. `min_propose_power` gates proposal creation on the
+    /// proposer's `proposer_power` (their registered `Voter.power`).
+    /// `min_action_delay` must match the timelock later passed to
+    /// `Proposal::execute`, so `execute_before` is rejected here if it would
+    /// leave no executable window once the timelock applies.
+    pub fn into_proposal(
+        &self,
+        min_duration: u32,
+        max_duration: u32,
+        min_propose_power: u16,
+        proposer_power: u16,
+        quorum_bps: u16,
+        total_power: u32,
+        min_action_delay: u32,
+    ) -> Proposal {
+        assert!(
+            proposer_power >= min_propose_power,
+            "proposer's voting power ({}) is below the threshold required to create a proposal ({})",
+            proposer_power,
+            min_propose_power
+        );
         let voting_start = u64::from(self.voting_start);
         let execute_before = u64::from(self.execute_before);
         let t: u64 = env::block_timestamp() / FROM_NANO;
@@ -168,21 +616,60 @@ impl NewProposal {
         );
         let voting_end = voting_start + u64::from(self.voting_duration);
         assert!(
-            execute_before > voting_end,
-            "execute_before must be after voting end"
+            execute_before > voting_end + u64::from(min_action_delay),
+            "execute_before must leave an executable window after voting_end + min_action_delay"
+        );
+        assert!(quorum_bps <= 10000, "quorum_bps must be in [0...10000]");
+        assert!(
+            !self.actions.is_empty() && self.actions.len() <= MAX_ACTIONS,
+            "a proposal must batch between 1 and {} actions",
+            MAX_ACTIONS
+        );
+        for action in &self.actions {
+            action.validate();
+        }
+        assert!(
+            self.actions.len() == 1
+                || !self
+                    .actions
+                    .iter()
+                    .any(|a| matches!(a, Action::Delete { .. })),
+            "a Delete action cannot be batched with any other action"
         );
-        return Proposal {
-            proposer: env::predecessor_account_id(),
+        let commit_end = u64::from(self.commit_end);
+        if self.commit_reveal {
+            assert!(
+                voting_start < commit_end && commit_end < voting_end,
+                "commit_end must fall between voting_start and voting_end"
+            );
+        }
+        let proposer = env::predecessor_account_id();
+        let proposal = Proposal {
+            proposer: proposer.clone(),
             description: self.description.clone(),
-            action: self.action.to_aint(),
-            voters: HashSet::new(),
+            actions: self.actions.iter().map(Action::to_aint).collect(),
+            voters: HashMap::new(),
             voting_start,
             voting_end,
             votes_for: 0,
             votes_against: 0,
             execute_before,
             executed: false,
+            quorum_bps,
+            total_power,
+            commit_reveal: self.commit_reveal,
+            commit_end,
+            commitments: HashMap::new(),
         };
+        Event::ProposalCreated(vec![ProposalCreatedData {
+            proposer: &proposer,
+            description: &proposal.description,
+            actions: proposal.actions.iter().map(ActionInt::to_action).collect(),
+            voting_start: proposal.voting_start.into(),
+            voting_end: proposal.voting_end.into(),
+        }])
+        .emit();
+        return proposal;
     }
 }
 
@@ -191,7 +678,7 @@ impl NewProposal {
 #[cfg_attr(feature = "test", derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
 pub struct ProposalOut {
-    pub action: Action,
+    pub actions: Vec<Action>,
     pub description: String,
     /// block number when voting started
     pub voting_start: U64,
@@ -201,12 +688,16 @@ pub struct ProposalOut {
     pub votes_against: u32,
     pub execute_before: U64,
     pub executed: bool,
+    pub quorum_bps: u16,
+    pub total_power: u32,
+    pub commit_reveal: bool,
+    pub commit_end: U64,
 }
 
 impl From<Proposal> for ProposalOut {
     fn from(p: Proposal) -> ProposalOut {
         ProposalOut {
-            action: p.action.into(),
+            actions: p.actions.into_iter().map(Into::into).collect(),
             description: p.description,
             voting_start: p.voting_start.into(),
             voting_end: p.voting_end.into(),
@@ -214,6 +705,10 @@ impl From<Proposal> for ProposalOut {
             votes_against: p.votes_against,
             execute_before: p.execute_before.into(),
             executed: p.executed,
+            quorum_bps: p.quorum_bps,
+            total_power: p.total_power,
+            commit_reveal: p.commit_reveal,
+            commit_end: p.commit_end.into(),
         }
     }
 }
@@ -226,3 +721,267 @@ pub fn assert_valid_account(a: &AccountId) {
         a
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{testing_env, MockedBlockchain, VMContext};
+
+    fn context(predecessor: &str, block_timestamp_secs: u64) -> VMContext {
+        VMContext {
+            current_account_id: "dao.near".to_string(),
+            signer_account_id: predecessor.to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: predecessor.to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: block_timestamp_secs * FROM_NANO,
+            account_balance: 0,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn voter(account: &str, power: u16) -> Voter {
+        Voter {
+            account: account.to_string(),
+            power,
+        }
+    }
+
+    fn sample_proposal(commit_reveal: bool, quorum_bps: u16, total_power: u32) -> Proposal {
+        Proposal {
+            proposer: "alice.near".to_string(),
+            description: "spend some funds".to_string(),
+            actions: vec![ActionInt::Transfer {
+                dest: "bob.near".to_string(),
+                amount: 1,
+            }],
+            voters: HashMap::new(),
+            voting_start: 100,
+            voting_end: 200,
+            votes_for: 0,
+            votes_against: 0,
+            execute_before: 300,
+            executed: false,
+            quorum_bps,
+            total_power,
+            commit_reveal,
+            commit_end: 150,
+            commitments: HashMap::new(),
+        }
+    }
+
+    fn commitment_for(account: &str, vote_yes: bool, salt: &[u8]) -> Vec<u8> {
+        let mut preimage = vec![vote_yes as u8];
+        preimage.extend_from_slice(salt);
+        preimage.extend_from_slice(account.as_bytes());
+        env::sha256(&preimage)
+    }
+
+    fn action_transfer(dest: &str, amount: u128) -> Action {
+        Action::Transfer {
+            dest: dest.to_string().try_into().unwrap(),
+            amount: amount.into(),
+        }
+    }
+
+    fn action_delete(dest: &str) -> Action {
+        Action::Delete {
+            dest: dest.to_string().try_into().unwrap(),
+        }
+    }
+
+    fn new_proposal(actions: Vec<Action>) -> NewProposal {
+        NewProposal {
+            actions,
+            description: "spend some funds".to_string(),
+            voting_start: 100u64.into(),
+            voting_duration: 100,
+            execute_before: 300u64.into(),
+            commit_reveal: false,
+            commit_end: 150u64.into(),
+        }
+    }
+
+    #[test]
+    fn conviction_power_follows_the_ladder_and_caps_at_the_top_rung() {
+        assert_eq!(conviction_power(100, 0), 10);
+        assert_eq!(conviction_power(100, 1), 100);
+        assert_eq!(conviction_power(100, 2), 200);
+        assert_eq!(conviction_power(100, 3), 400);
+        assert_eq!(conviction_power(100, 4), 800);
+        assert_eq!(
+            conviction_power(100, 9),
+            conviction_power(100, MAX_LOCK_PERIOD)
+        );
+    }
+
+    #[test]
+    fn commit_reveal_round_trip_applies_the_vote() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(true, 0, 1000);
+        let v = voter("alice.near", 50);
+        let salt = b"pepper";
+        p.commit(&v.account, commitment_for(&v.account, true, salt));
+
+        testing_env!(context("alice.near", 180));
+        p.reveal(&v, true, salt, 1, 0);
+
+        assert_eq!(p.votes_for, 50);
+        assert_eq!(p.votes_against, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "revealed vote doesn't match the committed hash")]
+    fn reveal_with_wrong_salt_is_rejected() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(true, 0, 1000);
+        let v = voter("alice.near", 50);
+        p.commit(&v.account, commitment_for(&v.account, true, b"pepper"));
+
+        testing_env!(context("alice.near", 180));
+        p.reveal(&v, true, b"wrong-salt", 1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "commit phase is not active")]
+    fn commit_after_commit_end_is_rejected() {
+        testing_env!(context("alice.near", 160));
+        let mut p = sample_proposal(true, 0, 1000);
+        p.commit(
+            &"alice.near".to_string(),
+            commitment_for("alice.near", true, b"pepper"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "reveal phase is not active")]
+    fn reveal_before_commit_end_is_rejected() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(true, 0, 1000);
+        let v = voter("alice.near", 50);
+        p.commit(&v.account, commitment_for(&v.account, true, b"pepper"));
+
+        testing_env!(context("alice.near", 140));
+        p.reveal(&v, true, b"pepper", 1, 0);
+    }
+
+    #[test]
+    fn execute_requires_reaching_quorum() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 2000, 1000);
+        p.vote(&voter("alice.near", 400), true, 1, 0);
+
+        testing_env!(context("alice.near", 250));
+        p.execute(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "proposal didn't reach quorum")]
+    fn execute_below_quorum_is_rejected() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 9000, 1000);
+        p.vote(&voter("alice.near", 400), true, 1, 0);
+
+        testing_env!(context("alice.near", 250));
+        p.execute(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is locked by a conviction vote")]
+    fn execute_delete_is_blocked_by_any_locked_voter_regardless_of_dest() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 0, 1000);
+        p.actions = vec![ActionInt::Delete {
+            dest: "someone-else.near".to_string(),
+        }];
+        p.vote(&voter("alice.near", 100), true, 4, 1000);
+
+        testing_env!(context("alice.near", 250));
+        p.execute(0, 0);
+    }
+
+    #[test]
+    fn revoke_removes_the_vote_from_the_tally() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 0, 1000);
+        p.vote(&voter("alice.near", 100), true, 0, 0);
+        assert_eq!(p.votes_for, 10);
+
+        p.revoke(&voter("alice.near", 100));
+        assert_eq!(p.votes_for, 0);
+        assert!(p.voters.is_empty());
+    }
+
+    #[test]
+    fn voting_again_replaces_the_previous_ballot() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 0, 1000);
+        p.vote(&voter("alice.near", 100), true, 0, 0);
+        assert_eq!(p.votes_for, 10);
+        assert_eq!(p.votes_against, 0);
+
+        p.vote(&voter("alice.near", 100), false, 0, 0);
+        assert_eq!(p.votes_for, 0);
+        assert_eq!(p.votes_against, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "a Delete action cannot be batched with any other action")]
+    fn into_proposal_rejects_batching_delete_with_other_actions() {
+        testing_env!(context("alice.near", 50));
+        let np = new_proposal(vec![
+            action_transfer("bob.near", 1),
+            action_delete("carol.near"),
+        ]);
+        np.into_proposal(0, 1000, 0, 0, 0, 1000, 0);
+    }
+
+    #[test]
+    fn execute_chains_a_batch_of_actions_into_one_promise() {
+        testing_env!(context("alice.near", 120));
+        let mut p = sample_proposal(false, 0, 1000);
+        p.actions = vec![
+            ActionInt::Transfer {
+                dest: "bob.near".to_string(),
+                amount: 1,
+            },
+            ActionInt::FunctionCall {
+                dest: "bob.near".to_string(),
+                method: "ping".to_string(),
+                args: vec![],
+                deposit: 0,
+                gas: 10_000_000_000_000,
+            },
+        ];
+        p.vote(&voter("alice.near", 100), true, 0, 0);
+
+        testing_env!(context("alice.near", 250));
+        p.execute(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is below the threshold required to create a proposal")]
+    fn into_proposal_rejects_proposer_below_min_power() {
+        testing_env!(context("alice.near", 50));
+        let np = new_proposal(vec![action_transfer("bob.near", 1)]);
+        np.into_proposal(0, 1000, 100, 1, 0, 1000, 0);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "execute_before must leave an executable window after voting_end + min_action_delay"
+    )]
+    fn into_proposal_rejects_an_unexecutable_window() {
+        testing_env!(context("alice.near", 50));
+        let np = new_proposal(vec![action_transfer("bob.near", 1)]);
+        np.into_proposal(0, 1000, 0, 0, 0, 1000, 200);
+    }
+}